@@ -3,10 +3,12 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
-#[derive(Deserialize)]
+mod search;
+mod uci;
+
+#[derive(Deserialize, Clone)]
 struct Settings {
     fen: String,
-    pieces: HashMap<char, char>,
     colors: HashMap<char, i32>,
     directions: HashMap<char, Vec<i32>>,
     rank_2: Vec<i32>,
@@ -19,32 +21,268 @@ enum Side {
     Black,
 }
 
-#[derive(Debug)]
+impl Side {
+    fn opposite(self) -> Side {
+        match self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+}
+
+/// Returns true if `index` lies within the playable 8x8 area of the padded
+/// 10x10 mailbox board (index 21..=98, excluding the border columns).
+fn is_valid_square(index: i32) -> bool {
+    if index < 21 || index > 98 {
+        return false;
+    }
+    let col = index % 10;
+    col >= 1 && col <= 8
+}
+
+/// Pushes a pawn move onto `move_list`, expanding a `Promotion`-flagged move
+/// into one move per promotion choice (queen, rook, bishop, knight) so a UCI
+/// client can pick any of them.
+fn push_pawn_move(move_list: &mut Vec<Move>, chess_move: Move, is_white: bool) {
+    if chess_move.kind != MoveKind::Promotion {
+        move_list.push(chess_move);
+        return;
+    }
+
+    let choices = if is_white { ['Q', 'R', 'B', 'N'] } else { ['q', 'r', 'b', 'n'] };
+    for promotion in choices {
+        move_list.push(Move {
+            promotion: Some(promotion),
+            ..chess_move
+        });
+    }
+}
+
+/// Maps an en-passant capture's target square to the square of the enemy
+/// pawn it removes, which sits one rank behind the target.
+fn en_passant_captured_square(target: usize, capturing_piece: char) -> usize {
+    if capturing_piece == 'P' {
+        target + 10
+    } else {
+        target - 10
+    }
+}
+
+/// Maps a castling king's destination square to the rook's (source, target)
+/// squares that move alongside it.
+fn castle_rook_squares(king_target: usize) -> (usize, usize) {
+    match king_target {
+        97 => (98, 96), // white kingside: h1 -> f1
+        93 => (91, 94), // white queenside: a1 -> d1
+        27 => (28, 26), // black kingside: h8 -> f8
+        23 => (21, 24), // black queenside: a8 -> d8
+        _ => unreachable!("castle move must target g1, c1, g8, or c8"),
+    }
+}
+
+/// Inverse of `parse_square`: maps a mailbox board index back to its
+/// algebraic square, e.g. 95 -> "e1".
+fn square_to_algebraic(square: usize) -> String {
+    let file = (square % 10) - 1;
+    let row = (square - 21 - file) / 10;
+    let rank = 8 - row;
+    format!("{}{}", (b'a' + file as u8) as char, rank)
+}
+
+/// Formats a move in long algebraic notation (e.g. "e2e4", "e7e8q"), the
+/// form UCI clients send and expect back.
+fn move_to_uci(chess_move: &Move) -> String {
+    let mut uci = format!(
+        "{}{}",
+        square_to_algebraic(chess_move.source),
+        square_to_algebraic(chess_move.target)
+    );
+    if let Some(promotion) = chess_move.promotion {
+        uci.push(promotion.to_ascii_lowercase());
+    }
+    uci
+}
+
+/// Maps an algebraic square such as "e3" to its index in the padded 10x10
+/// mailbox board, rejecting anything that isn't a valid en-passant target
+/// (i.e. not on rank 3 or rank 6).
+fn parse_square(square: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut chars = square.chars();
+    let file = match chars.next() {
+        Some(c @ 'a'..='h') => c as i32 - 'a' as i32,
+        _ => return Err(format!("Invalid square: {square}").into()),
+    };
+    let rank: i32 = match chars.next() {
+        Some(c @ '1'..='8') => c.to_digit(10).unwrap() as i32,
+        _ => return Err(format!("Invalid square: {square}").into()),
+    };
+    if chars.next().is_some() {
+        return Err(format!("Invalid square: {square}").into());
+    }
+    if rank != 3 && rank != 6 {
+        return Err(format!("Invalid en passant target: {square}").into());
+    }
+    Ok((21 + file + (7 - (rank - 1)) * 10) as usize)
+}
+
+/// Flags the special handling `make_move`/`take_back` must apply on top of
+/// the plain source/target board update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MoveKind {
+    Normal,
+    Castle,
+    EnPassant,
+    Promotion,
+}
+
+/// State that isn't reconstructable from the move itself, captured before
+/// the move is made so `take_back` can restore it exactly.
+#[derive(Debug, Clone, Copy)]
+struct Undo {
+    castle_rights: [bool; 4],
+    en_passant: Option<usize>,
+    halfmove: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
 struct Move {
     source: usize,
     target: usize,
     piece: char,
     captured_piece: char,
+    kind: MoveKind,
+    // The piece a promotion resolves to (e.g. 'Q' or 'n'); `None` otherwise.
+    promotion: Option<char>,
+    undo: Undo,
+}
+
+/// Piece types a Zobrist key is drawn for, matching the board's own piece
+/// characters.
+const ZOBRIST_PIECES: [char; 12] = ['P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k'];
+
+/// Fixed seed so every run assigns the same keys to the same (piece, square)
+/// pairs, making hashes reproducible across runs.
+const ZOBRIST_SEED: u64 = 0x9E3779B97F4A7C15;
+
+/// SplitMix64, a small well-distributed generator, good enough for assigning
+/// one-off Zobrist keys.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Maps a board index to its file (0 = a-file .. 7 = h-file), used to key
+/// the en-passant component of the hash.
+fn file_of(square: usize) -> usize {
+    (square % 10) - 1
+}
+
+/// Random keys XOR-ed together to give a position a stable 64-bit identity.
+/// One key per (piece, square) pair, one for side-to-move, four for castle
+/// rights, and eight for the en-passant file.
+struct Zobrist {
+    piece_square: Vec<[u64; 100]>,
+    side: u64,
+    castle: [u64; 4],
+    ep_file: [u64; 8],
+}
+
+impl Zobrist {
+    fn new() -> Self {
+        let mut state = ZOBRIST_SEED;
+        let piece_square = ZOBRIST_PIECES
+            .iter()
+            .map(|_| std::array::from_fn(|_| splitmix64(&mut state)))
+            .collect();
+        let side = splitmix64(&mut state);
+        let castle = std::array::from_fn(|_| splitmix64(&mut state));
+        let ep_file = std::array::from_fn(|_| splitmix64(&mut state));
+
+        Zobrist {
+            piece_square,
+            side,
+            castle,
+            ep_file,
+        }
+    }
+
+    fn piece_key(&self, piece: char, square: usize) -> u64 {
+        let piece_index = ZOBRIST_PIECES
+            .iter()
+            .position(|&c| c == piece)
+            .expect("zobrist table covers every board piece");
+        self.piece_square[piece_index][square]
+    }
+
+    /// Computes a position's hash from scratch; only used when a position is
+    /// built from a FEN. From there `make_move` updates the hash
+    /// incrementally and `take_back` restores the previous one from history.
+    fn hash_position(
+        &self,
+        board: &[char],
+        side: Side,
+        castle_rights: [bool; 4],
+        en_passant: Option<usize>,
+    ) -> u64 {
+        let mut hash = 0u64;
+
+        for (square, &piece) in board.iter().enumerate() {
+            if ZOBRIST_PIECES.contains(&piece) {
+                hash ^= self.piece_key(piece, square);
+            }
+        }
+
+        if side == Side::Black {
+            hash ^= self.side;
+        }
+
+        for (right, &held) in self.castle.iter().zip(castle_rights.iter()) {
+            if held {
+                hash ^= right;
+            }
+        }
+
+        if let Some(square) = en_passant {
+            hash ^= self.ep_file[file_of(square)];
+        }
+
+        hash
+    }
 }
 
 struct Chess {
     board: Vec<char>,
     side: Side,
-    pieces: HashMap<char, char>,
     colors: HashMap<char, i32>,
     directions: HashMap<char, Vec<i32>>,
     rank_2: Vec<i32>,
     rank_7: Vec<i32>,
+    // Castling rights in [WK, WQ, BK, BQ] order.
+    castle_rights: [bool; 4],
+    en_passant: Option<usize>,
+    halfmove: u32,
+    zobrist: Zobrist,
+    hash: u64,
+    // Hash of every position reached so far, for threefold-repetition checks.
+    history: Vec<u64>,
 }
 
-impl Chess {
-    fn new(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        // Read and parse settings.json
-        let mut file = File::open(filename)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let settings: Settings = serde_json::from_str(&contents)?;
+/// Reads and parses a settings file such as settings.json.
+pub(crate) fn load_settings(filename: &str) -> Result<Settings, Box<dyn std::error::Error>> {
+    let mut file = File::open(filename)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(serde_json::from_str(&contents)?)
+}
 
+impl Chess {
+    /// Builds a position from an already-loaded `Settings`, separate from
+    /// `load_settings` so callers that already hold config (tests, the UCI
+    /// front end switching positions) don't have to round-trip through a file.
+    fn from_settings(settings: Settings) -> Result<Self, Box<dyn std::error::Error>> {
         // Parse FEN
         let fen_parts: Vec<&str> = settings.fen.split_whitespace().collect();
         if fen_parts.len() < 2 {
@@ -91,49 +329,67 @@ impl Chess {
             _ => return Err("Invalid FEN: side must be 'w' or 'b'".into()),
         };
 
+        // Castling availability (e.g. "KQkq", "Kq", "-")
+        let castling = fen_parts.get(2).copied().unwrap_or("-");
+        let castle_rights = [
+            castling.contains('K'),
+            castling.contains('Q'),
+            castling.contains('k'),
+            castling.contains('q'),
+        ];
+
+        // En passant target square (e.g. "e3", "-")
+        let en_passant = match fen_parts.get(3).copied().unwrap_or("-") {
+            "-" => None,
+            square => Some(parse_square(square)?),
+        };
+
+        // Halfmove clock, defaulting when absent
+        let halfmove = match fen_parts.get(4) {
+            Some(field) => field.parse().map_err(|_| "Invalid FEN: bad halfmove clock")?,
+            None => 0,
+        };
+        // Fullmove number isn't tracked by the engine; still validate it so a
+        // malformed FEN is rejected.
+        if let Some(field) = fen_parts.get(5) {
+            field
+                .parse::<u32>()
+                .map_err(|_| "Invalid FEN: bad fullmove number")?;
+        }
+
+        let zobrist = Zobrist::new();
+        let hash = zobrist.hash_position(&board, side, castle_rights, en_passant);
+
         Ok(Chess {
             board,
             side,
-            pieces: settings.pieces,
             colors: settings.colors,
             directions: settings.directions,
             rank_2: settings.rank_2,
             rank_7: settings.rank_7,
+            castle_rights,
+            en_passant,
+            halfmove,
+            zobrist,
+            hash,
+            history: vec![hash],
         })
     }
 
-    fn print_board(&self) {
-        let board_str: String = self
-            .board
-            .iter()
-            .map(|&c| {
-                if c == '\n' {
-                    "\n".to_string()
-                } else {
-                    format!(" {}", self.pieces.get(&c).unwrap_or(&c))
-                }
-            })
-            .collect();
-        let side_num = match self.side {
-            Side::White => 0,
-            Side::Black => 1,
-        };
-        println!("{}\n{}", board_str, side_num);
+    /// Snapshot of the state a move needs to undo, besides the board itself.
+    fn undo_state(&self) -> Undo {
+        Undo {
+            castle_rights: self.castle_rights,
+            en_passant: self.en_passant,
+            halfmove: self.halfmove,
+        }
     }
 
     fn generate_moves(&self) -> Vec<Move> {
         let mut move_list: Vec<Move> = Vec::new();
+        let undo = self.undo_state();
 
-        // Helper function to check if a square is within the 8x8 board
-        fn is_valid_square(index: i32) -> bool {
-            // Playable board is from index 21 (a1) to 98 (h8), excluding borders
-            if index < 21 || index > 98 {
-                return false;
-            }
-            // Check if the square is in the playable 8x8 area (columns 1-8)
-            let col = index % 10;
-            col >= 1 && col <= 8
-        }
+        self.generate_castle_moves(&mut move_list);
 
         for i in 0..self.board.len() {
             let piece = self.board[i];
@@ -169,16 +425,30 @@ impl Chess {
                     let double_forward = if is_white { -20 } else { 20 };
                     let captures = if is_white { [-11, -9] } else { [9, 11] };
 
+                    let promotion_rank = if is_white { &self.rank_7 } else { &self.rank_2 };
+                    let kind = if promotion_rank.contains(&(i as i32)) {
+                        MoveKind::Promotion
+                    } else {
+                        MoveKind::Normal
+                    };
+
                     // Single forward move
                     if offset == forward && is_valid_square(target_square) {
                         let captured_piece = self.board[target_square as usize];
                         if captured_piece == '.' {
-                            move_list.push(Move {
-                                source: i,
-                                target: target_square as usize,
-                                piece,
-                                captured_piece,
-                            });
+                            push_pawn_move(
+                                &mut move_list,
+                                Move {
+                                    source: i,
+                                    target: target_square as usize,
+                                    piece,
+                                    captured_piece,
+                                    kind,
+                                    promotion: None,
+                                    undo,
+                                },
+                                is_white,
+                            );
                         }
                     }
 
@@ -195,27 +465,49 @@ impl Chess {
                                 target: target_square as usize,
                                 piece,
                                 captured_piece: '.',
+                                kind: MoveKind::Normal,
+                                promotion: None,
+                                undo,
                             });
                         }
                     }
 
-                    // Captures
+                    // Captures, including en passant
                     if captures.contains(&offset) && is_valid_square(target_square) {
-                        let captured_piece = self.board[target_square as usize];
+                        let target_usize = target_square as usize;
+                        let captured_piece = self.board[target_usize];
                         if captured_piece != '.'
                             && captured_piece != ' '
                             && captured_piece != '\n'
                         {
                             if let Some(&captured_side) = self.colors.get(&captured_piece) {
                                 if captured_side != piece_side {
-                                    move_list.push(Move {
-                                        source: i,
-                                        target: target_square as usize,
-                                        piece,
-                                        captured_piece,
-                                    });
+                                    push_pawn_move(
+                                        &mut move_list,
+                                        Move {
+                                            source: i,
+                                            target: target_usize,
+                                            piece,
+                                            captured_piece,
+                                            kind,
+                                            promotion: None,
+                                            undo,
+                                        },
+                                        is_white,
+                                    );
                                 }
                             }
+                        } else if self.is_en_passant_capture(target_usize, piece) {
+                            let captured_square = en_passant_captured_square(target_usize, piece);
+                            move_list.push(Move {
+                                source: i,
+                                target: target_usize,
+                                piece,
+                                captured_piece: self.board[captured_square],
+                                kind: MoveKind::EnPassant,
+                                promotion: None,
+                                undo,
+                            });
                         }
                     }
 
@@ -240,6 +532,9 @@ impl Chess {
                             target: target_square as usize,
                             piece,
                             captured_piece,
+                            kind: MoveKind::Normal,
+                            promotion: None,
+                            undo,
                         });
                     } else {
                         // Handle capture
@@ -250,6 +545,9 @@ impl Chess {
                                     target: target_square as usize,
                                     piece,
                                     captured_piece,
+                                    kind: MoveKind::Normal,
+                                    promotion: None,
+                                    undo,
                                 });
                             }
                         }
@@ -270,45 +568,502 @@ impl Chess {
         move_list
     }
 
+    /// Appends a king two-square move for each castling right that's still
+    /// held, the squares between king and rook are empty, and neither the
+    /// king's start, transit, nor destination square is attacked.
+    fn generate_castle_moves(&self, move_list: &mut Vec<Move>) {
+        let castle_rights = self.castle_rights;
+        let enemy = self.side.opposite();
+
+        // `empty_squares` must be unoccupied; `safe_squares` (the king's
+        // start square plus every square it passes through) must not be
+        // attacked. For queenside castling the rook's path includes a square
+        // the king never crosses, so the two lists differ.
+        let mut try_castle = |right: bool,
+                              king_source: usize,
+                              king_target: usize,
+                              empty_squares: &[usize],
+                              safe_squares: &[usize]| {
+            if right
+                && empty_squares.iter().all(|&sq| self.board[sq] == '.')
+                && safe_squares.iter().all(|&sq| !self.is_square_attacked(sq, enemy))
+            {
+                let piece = if self.side == Side::White { 'K' } else { 'k' };
+                move_list.push(Move {
+                    source: king_source,
+                    target: king_target,
+                    piece,
+                    captured_piece: '.',
+                    kind: MoveKind::Castle,
+                    promotion: None,
+                    undo: self.undo_state(),
+                });
+            }
+        };
+
+        match self.side {
+            Side::White => {
+                try_castle(castle_rights[0], 95, 97, &[96, 97], &[95, 96, 97]);
+                try_castle(castle_rights[1], 95, 93, &[94, 93, 92], &[95, 94, 93]);
+            }
+            Side::Black => {
+                try_castle(castle_rights[2], 25, 27, &[26, 27], &[25, 26, 27]);
+                try_castle(castle_rights[3], 25, 23, &[24, 23, 22], &[25, 24, 23]);
+            }
+        }
+    }
+
     fn make_move(&mut self, chess_move: &Move) {
+        let mut hash = self.hash;
+
+        // XOR out the moving piece and whatever it captures.
+        hash ^= self.zobrist.piece_key(chess_move.piece, chess_move.source);
+        if chess_move.kind == MoveKind::EnPassant {
+            let captured_square = en_passant_captured_square(chess_move.target, chess_move.piece);
+            hash ^= self.zobrist.piece_key(chess_move.captured_piece, captured_square);
+        } else if chess_move.captured_piece != '.' {
+            hash ^= self.zobrist.piece_key(chess_move.captured_piece, chess_move.target);
+        }
+
         self.board[chess_move.target] = chess_move.piece;
         self.board[chess_move.source] = '.';
-        if chess_move.piece == 'P' && self.rank_7.contains(&(chess_move.source as i32)) {
-            self.board[chess_move.target] = 'Q';
+        if let Some(promotion) = chess_move.promotion {
+            self.board[chess_move.target] = promotion;
         }
-        if chess_move.piece == 'p' && self.rank_2.contains(&(chess_move.source as i32)) {
-            self.board[chess_move.target] = 'q';
+        // XOR in whatever landed on the target square (promotion may differ
+        // from the piece that left the source square).
+        hash ^= self.zobrist.piece_key(self.board[chess_move.target], chess_move.target);
+
+        if chess_move.kind == MoveKind::Castle {
+            let (rook_source, rook_target) = castle_rook_squares(chess_move.target);
+            let rook = self.board[rook_source];
+            hash ^= self.zobrist.piece_key(rook, rook_source);
+            hash ^= self.zobrist.piece_key(rook, rook_target);
+            self.board[rook_target] = rook;
+            self.board[rook_source] = '.';
         }
 
-        self.print_board();
+        if chess_move.kind == MoveKind::EnPassant {
+            let captured_square = en_passant_captured_square(chess_move.target, chess_move.piece);
+            self.board[captured_square] = '.';
+        }
 
-        self.side = match self.side {
-            Side::White => Side::Black,
-            Side::Black => Side::White,
+        let castle_rights_before = self.castle_rights;
+        self.clear_castle_rights_for_square(chess_move.source);
+        self.clear_castle_rights_for_square(chess_move.target);
+        for (i, &before) in castle_rights_before.iter().enumerate() {
+            if before != self.castle_rights[i] {
+                hash ^= self.zobrist.castle[i];
+            }
+        }
+
+        if let Some(square) = self.en_passant {
+            hash ^= self.zobrist.ep_file[file_of(square)];
+        }
+        self.en_passant = self.double_push_target(chess_move);
+        if let Some(square) = self.en_passant {
+            hash ^= self.zobrist.ep_file[file_of(square)];
+        }
+
+        hash ^= self.zobrist.side;
+
+        self.halfmove = if matches!(chess_move.piece, 'P' | 'p') || chess_move.captured_piece != '.' {
+            0
+        } else {
+            self.halfmove + 1
         };
+
+        self.side = self.side.opposite();
+        self.hash = hash;
+        self.history.push(hash);
     }
 
     fn take_back(&mut self, chess_move: &Move) {
-        self.board[chess_move.target] = chess_move.captured_piece;
+        if chess_move.kind == MoveKind::EnPassant {
+            self.board[chess_move.target] = '.';
+            let captured_square = en_passant_captured_square(chess_move.target, chess_move.piece);
+            self.board[captured_square] = chess_move.captured_piece;
+        } else {
+            self.board[chess_move.target] = chess_move.captured_piece;
+        }
         self.board[chess_move.source] = chess_move.piece;
 
-        self.print_board();
+        if chess_move.kind == MoveKind::Castle {
+            let (rook_source, rook_target) = castle_rook_squares(chess_move.target);
+            self.board[rook_source] = self.board[rook_target];
+            self.board[rook_target] = '.';
+        }
 
-        self.side = match self.side {
-            Side::White => Side::Black,
-            Side::Black => Side::White,
+        self.castle_rights = chess_move.undo.castle_rights;
+        self.en_passant = chess_move.undo.en_passant;
+        self.halfmove = chess_move.undo.halfmove;
+
+        self.side = self.side.opposite();
+        self.history.pop();
+        self.hash = *self
+            .history
+            .last()
+            .expect("history always holds at least the starting position");
+    }
+
+    /// True on a third occurrence of the current position, or when the
+    /// fifty-move rule's halfmove clock reaches 100.
+    fn is_draw(&self) -> bool {
+        self.halfmove >= 100
+            || self.history.iter().filter(|&&h| h == self.hash).count() >= 3
+    }
+
+    /// If `chess_move` is a pawn double push, returns the square it skipped
+    /// over — the new en-passant target. Otherwise `None`, since the right
+    /// only survives for the one ply right after the push.
+    fn double_push_target(&self, chess_move: &Move) -> Option<usize> {
+        if !matches!(chess_move.piece, 'P' | 'p') {
+            return None;
+        }
+        let diff = chess_move.target as i32 - chess_move.source as i32;
+        if diff == -20 {
+            Some(chess_move.source - 10)
+        } else if diff == 20 {
+            Some(chess_move.source + 10)
+        } else {
+            None
+        }
+    }
+
+    /// True if a pawn capture from `piece`'s side landing on `target` would
+    /// be an en-passant capture: `target` is the live en-passant square, it's
+    /// empty, sits on rank 3 or 6, and an enemy pawn sits directly behind it.
+    fn is_en_passant_capture(&self, target: usize, piece: char) -> bool {
+        if self.en_passant != Some(target) || self.board[target] != '.' {
+            return false;
+        }
+        let enemy_pawn = if piece == 'P' { 'p' } else { 'P' };
+        self.board[en_passant_captured_square(target, piece)] == enemy_pawn
+    }
+
+    /// Revokes castling rights tied to `square`, whether it held the king
+    /// that just moved or a rook that moved or was captured.
+    fn clear_castle_rights_for_square(&mut self, square: usize) {
+        match square {
+            91 => self.castle_rights[1] = false, // a1 rook: white queenside
+            98 => self.castle_rights[0] = false, // h1 rook: white kingside
+            21 => self.castle_rights[3] = false, // a8 rook: black queenside
+            28 => self.castle_rights[2] = false, // h8 rook: black kingside
+            95 => self.castle_rights[0..2].fill(false), // e1 king
+            25 => self.castle_rights[2..4].fill(false), // e8 king
+            _ => {}
+        }
+    }
+
+    /// Finds the square of the given side's king.
+    fn king_square(&self, side: Side) -> usize {
+        let king = match side {
+            Side::White => 'K',
+            Side::Black => 'k',
+        };
+        self.board
+            .iter()
+            .position(|&c| c == king)
+            .expect("a king must be on the board")
+    }
+
+    /// Returns true if `square` is attacked by any piece belonging to `by_side`.
+    ///
+    /// Scans outward from `square` along the knight, king, pawn-capture, and
+    /// sliding directions, reusing the same offsets `generate_moves` uses, so
+    /// an attacker is found regardless of whose move it is.
+    fn is_square_attacked(&self, square: usize, by_side: Side) -> bool {
+        let (knight, bishop, rook, queen, king, pawn) = match by_side {
+            Side::White => ('N', 'B', 'R', 'Q', 'K', 'P'),
+            Side::Black => ('n', 'b', 'r', 'q', 'k', 'p'),
+        };
+
+        // Pawns attack diagonally forward, so look behind `square` from the
+        // attacker's point of view.
+        let pawn_offsets: [i32; 2] = match by_side {
+            Side::White => [11, 9],
+            Side::Black => [-11, -9],
         };
+        for &offset in &pawn_offsets {
+            let source = square as i32 + offset;
+            if is_valid_square(source) && self.board[source as usize] == pawn {
+                return true;
+            }
+        }
+
+        // Knights and kings jump directly to their target square.
+        for &(piece, offsets) in &[
+            (knight, self.directions.get(&knight)),
+            (king, self.directions.get(&king)),
+        ] {
+            if let Some(offsets) = offsets {
+                for &offset in offsets {
+                    let source = square as i32 + offset;
+                    if is_valid_square(source) && self.board[source as usize] == piece {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        // Bishops/rooks slide until blocked; a queen attacks along both.
+        for &(piece, offsets) in &[
+            (bishop, self.directions.get(&bishop)),
+            (rook, self.directions.get(&rook)),
+        ] {
+            if let Some(offsets) = offsets {
+                for &offset in offsets {
+                    let mut target = square as i32 + offset;
+                    while is_valid_square(target) {
+                        let occupant = self.board[target as usize];
+                        if occupant == '.' {
+                            target += offset;
+                            continue;
+                        }
+                        if occupant == piece || occupant == queen {
+                            return true;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// True if the side to move's king is currently attacked.
+    fn in_check(&self) -> bool {
+        self.is_square_attacked(self.king_square(self.side), self.side.opposite())
+    }
+
+    /// Filters `generate_moves` down to moves that don't leave the mover's
+    /// own king in check.
+    fn generate_legal_moves(&mut self) -> Vec<Move> {
+        let mover = self.side;
+        let pseudo_legal = self.generate_moves();
+        let mut legal = Vec::new();
+
+        for chess_move in pseudo_legal {
+            self.make_move(&chess_move);
+            let leaves_king_safe = !self.is_square_attacked(self.king_square(mover), mover.opposite());
+            self.take_back(&chess_move);
+            if leaves_king_safe {
+                legal.push(chess_move);
+            }
+        }
+
+        legal
+    }
+
+    /// Returns the game's terminal state given its already-generated legal
+    /// moves, or `None` if any remain. Takes the list rather than generating
+    /// it itself so callers that need both don't generate it twice.
+    fn terminal_state(&self, legal: &[Move]) -> Option<GameResult> {
+        if !legal.is_empty() {
+            return None;
+        }
+        Some(if self.in_check() {
+            GameResult::Checkmate
+        } else {
+            GameResult::Stalemate
+        })
+    }
+
+    /// Counts leaf nodes at `depth` plies, the standard way to validate a
+    /// move generator against known node counts.
+    fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mut nodes = 0;
+        for chess_move in self.generate_legal_moves() {
+            self.make_move(&chess_move);
+            nodes += self.perft(depth - 1);
+            self.take_back(&chess_move);
+        }
+        nodes
+    }
+
+    /// Like `perft`, but prints each root move's own subtree count, which is
+    /// the standard way to localize a move-generation bug to a single move.
+    fn perft_divide(&mut self, depth: u32) -> u64 {
+        let mut total = 0;
+        for chess_move in self.generate_legal_moves() {
+            self.make_move(&chess_move);
+            let nodes = self.perft(depth - 1);
+            self.take_back(&chess_move);
+            println!("{}: {}", move_to_uci(&chess_move), nodes);
+            total += nodes;
+        }
+        println!("\nNodes searched: {total}");
+        total
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum GameResult {
+    Checkmate,
+    Stalemate,
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut chess = Chess::new("settings.json")?;
-    let move_list: Vec<Move> = chess.generate_moves();
+    uci::run("settings.json")
+}
+
+/// Builds a `Settings` from `fen` plus the standard piece config, so tests
+/// (in this file and in other modules) don't depend on an on-disk
+/// settings.json.
+#[cfg(test)]
+fn test_settings(fen: &str) -> Settings {
+    let colors: HashMap<char, i32> = "PNBRQK"
+        .chars()
+        .map(|c| (c, 0))
+        .chain("pnbrqk".chars().map(|c| (c, 1)))
+        .collect();
+    let directions: HashMap<char, Vec<i32>> = HashMap::from([
+        ('P', vec![-10, -20, -11, -9]),
+        ('p', vec![10, 20, 9, 11]),
+        ('N', vec![-21, -19, -12, -8, 8, 12, 19, 21]),
+        ('n', vec![-21, -19, -12, -8, 8, 12, 19, 21]),
+        ('B', vec![-11, -9, 9, 11]),
+        ('b', vec![-11, -9, 9, 11]),
+        ('R', vec![-10, -1, 1, 10]),
+        ('r', vec![-10, -1, 1, 10]),
+        ('Q', vec![-11, -10, -9, -1, 1, 9, 10, 11]),
+        ('q', vec![-11, -10, -9, -1, 1, 9, 10, 11]),
+        ('K', vec![-11, -10, -9, -1, 1, 9, 10, 11]),
+        ('k', vec![-11, -10, -9, -1, 1, 9, 10, 11]),
+    ]);
+
+    Settings {
+        fen: fen.to_string(),
+        colors,
+        directions,
+        rank_2: vec![81, 82, 83, 84, 85, 86, 87, 88],
+        rank_7: vec![31, 32, 33, 34, 35, 36, 37, 38],
+    }
+}
 
-    for move_item in move_list.iter() {
-        chess.make_move(move_item);
-        chess.take_back(move_item);
+#[cfg(test)]
+pub(crate) fn chess_from_fen(fen: &str) -> Chess {
+    Chess::from_settings(test_settings(fen)).expect("test FEN must parse")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_castling_rights_en_passant_and_halfmove_clock() {
+        let chess = chess_from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R b KQkq d6 12 34");
+        assert_eq!(chess.castle_rights, [true, true, true, true]);
+        assert_eq!(chess.en_passant, Some(parse_square("d6").unwrap()));
+        assert_eq!(chess.halfmove, 12);
+
+        let chess = chess_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+        assert_eq!(chess.castle_rights, [true, false, false, true]);
+        assert_eq!(chess.en_passant, None);
+        assert_eq!(chess.halfmove, 0);
+    }
+
+    #[test]
+    fn rejects_an_unparsable_fullmove_number() {
+        let settings = test_settings("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 oops");
+        assert!(Chess::from_settings(settings).is_err());
+    }
+
+    #[test]
+    fn castle_rights_revoked_by_rook_move_and_restored_by_take_back() {
+        let mut chess = chess_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let original_rights = chess.castle_rights;
+
+        let legal = chess.generate_legal_moves();
+        let rook_move = legal
+            .into_iter()
+            .find(|m| m.piece == 'R' && m.source == 98)
+            .expect("h1 rook should have a legal move");
+
+        chess.make_move(&rook_move);
+        assert_eq!(chess.castle_rights, [false, true, true, true]);
+
+        chess.take_back(&rook_move);
+        assert_eq!(chess.castle_rights, original_rights);
     }
 
-    Ok(())
+    #[test]
+    fn en_passant_capture_removes_pawn_and_take_back_restores_board() {
+        let mut chess =
+            chess_from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3");
+        let original_board = chess.board.clone();
+
+        let legal = chess.generate_legal_moves();
+        let ep_move = legal
+            .into_iter()
+            .find(|m| m.kind == MoveKind::EnPassant)
+            .expect("en passant capture should be legal");
+
+        chess.make_move(&ep_move);
+        assert_eq!(chess.board[ep_move.target], 'P');
+        assert_eq!(
+            chess.board[en_passant_captured_square(ep_move.target, ep_move.piece)],
+            '.'
+        );
+
+        chess.take_back(&ep_move);
+        assert_eq!(chess.board, original_board);
+    }
+
+    #[test]
+    fn fifty_move_and_repetition_draws_are_detected() {
+        let mut chess = chess_from_fen("8/8/8/4k3/8/8/8/4K3 w - - 0 1");
+
+        // Shuffle the kings back and forth twice, repeating the starting
+        // position two more times (three occurrences total).
+        let shuffle = [("e1", "d1"), ("e5", "d5"), ("d1", "e1"), ("d5", "e5")];
+        for _ in 0..2 {
+            for &(from, to) in &shuffle {
+                let legal = chess.generate_legal_moves();
+                let chess_move = legal
+                    .into_iter()
+                    .find(|m| {
+                        square_to_algebraic(m.source) == from && square_to_algebraic(m.target) == to
+                    })
+                    .expect("king shuffle move should be legal");
+                chess.make_move(&chess_move);
+            }
+        }
+        assert!(chess.is_draw());
+
+        let mut chess = chess_from_fen("8/8/8/4k3/8/8/8/4K3 w - - 99 1");
+        let quiet_move = chess
+            .generate_legal_moves()
+            .into_iter()
+            .next()
+            .expect("a quiet king move exists");
+        chess.make_move(&quiet_move);
+        assert!(chess.is_draw());
+    }
+
+    #[test]
+    fn perft_start_position() {
+        let expected = [20, 400, 8902, 197281];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            let mut chess =
+                chess_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+            assert_eq!(chess.perft(depth as u32 + 1), nodes);
+        }
+    }
+
+    // "Kiwipete", the standard position for exercising castling, en
+    // passant, and promotions together.
+    #[test]
+    fn perft_kiwipete() {
+        let mut chess = chess_from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        );
+        assert_eq!(chess.perft(1), 48);
+        assert_eq!(chess.perft(2), 2039);
+    }
 }
\ No newline at end of file
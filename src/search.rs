@@ -0,0 +1,228 @@
+//! Negamax search with alpha-beta pruning over a material + piece-square
+//! evaluation, so the engine can actually choose a move instead of just
+//! enumerating them.
+
+use super::*;
+
+/// Score assigned to a forced mate, offset by ply-to-mate so shorter mates
+/// are preferred over longer ones.
+const MATE_SCORE: i32 = 1_000_000;
+
+fn piece_value(piece: char) -> i32 {
+    match piece.to_ascii_uppercase() {
+        'P' => 100,
+        'N' => 320,
+        'B' => 330,
+        'R' => 500,
+        'Q' => 900,
+        _ => 0,
+    }
+}
+
+#[rustfmt::skip]
+const PAWN_PST: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_PST: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_PST: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_PST: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_PST: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_PST: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// Looks up a piece-square bonus for `piece` on `square`, mirroring the
+/// table vertically for black so both sides share the same tables from
+/// their own side's perspective.
+fn pst_value(piece: char, square: usize) -> i32 {
+    let table = match piece.to_ascii_uppercase() {
+        'P' => &PAWN_PST,
+        'N' => &KNIGHT_PST,
+        'B' => &BISHOP_PST,
+        'R' => &ROOK_PST,
+        'Q' => &QUEEN_PST,
+        'K' => &KING_PST,
+        _ => return 0,
+    };
+    let file = file_of(square);
+    let row = (square - 21 - file) / 10;
+    let row = if piece.is_uppercase() { row } else { 7 - row };
+    table[row * 8 + file]
+}
+
+/// MVV-LVA ordering score: victim value minus attacker value, 0 for quiet
+/// moves. The king counts as 0, and a capture of a cheaper piece by a dearer
+/// one scores below 0, so it sorts after the quiet moves.
+fn mvv_lva_score(chess_move: &Move) -> i32 {
+    if chess_move.captured_piece == '.' {
+        return 0;
+    }
+    piece_value(chess_move.captured_piece) - piece_value(chess_move.piece)
+}
+
+impl Chess {
+    /// Material balance plus piece-square bonuses, from the side-to-move's
+    /// perspective.
+    fn evaluate(&self) -> i32 {
+        let mut score = 0;
+        for (square, &piece) in self.board.iter().enumerate() {
+            if !ZOBRIST_PIECES.contains(&piece) {
+                continue;
+            }
+            let value = piece_value(piece) + pst_value(piece, square);
+            score += if piece.is_uppercase() { value } else { -value };
+        }
+        if self.side == Side::White {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Negamax alpha-beta search. Returns the best move and its score from
+    /// the side-to-move's perspective.
+    pub(crate) fn search(&mut self, depth: u32) -> (Option<Move>, i32) {
+        let mut legal = self.generate_legal_moves();
+        legal.sort_by_key(|m| std::cmp::Reverse(mvv_lva_score(m)));
+
+        let mut alpha = -MATE_SCORE - 1;
+        let beta = MATE_SCORE + 1;
+        let mut best_move = None;
+        let mut best_score = alpha;
+
+        for chess_move in legal {
+            self.make_move(&chess_move);
+            let score = -self.negamax(depth.saturating_sub(1), -beta, -alpha, 1);
+            self.take_back(&chess_move);
+
+            if score > best_score || best_move.is_none() {
+                best_score = score;
+                best_move = Some(chess_move);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        (best_move, best_score)
+    }
+
+    fn negamax(&mut self, depth: u32, mut alpha: i32, beta: i32, ply: u32) -> i32 {
+        if self.is_draw() {
+            return 0;
+        }
+        let mut legal = self.generate_legal_moves();
+        if let Some(result) = self.terminal_state(&legal) {
+            return match result {
+                GameResult::Checkmate => -MATE_SCORE + ply as i32,
+                GameResult::Stalemate => 0,
+            };
+        }
+        if depth == 0 {
+            return self.evaluate();
+        }
+
+        legal.sort_by_key(|m| std::cmp::Reverse(mvv_lva_score(m)));
+
+        let mut best = -MATE_SCORE - 1;
+        for chess_move in legal {
+            self.make_move(&chess_move);
+            let score = -self.negamax(depth - 1, -beta, -alpha, ply + 1);
+            self.take_back(&chess_move);
+
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_finds_mate_in_one() {
+        let mut chess = chess_from_fen("6k1/5ppp/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let (best_move, score) = chess.search(3);
+        let best_move = best_move.expect("mate in one should report a move");
+        assert_eq!(move_to_uci(&best_move), "a1a8");
+        assert_eq!(score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn evaluate_favors_the_side_with_more_material() {
+        let chess = chess_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert!(chess.evaluate() > 0);
+
+        let chess = chess_from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        assert!(chess.evaluate() < 0);
+    }
+}
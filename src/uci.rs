@@ -0,0 +1,119 @@
+//! Universal Chess Interface front end: reads UCI commands from stdin and
+//! writes responses to stdout, so the engine can be driven by a chess GUI.
+
+use super::*;
+use std::io::{self, BufRead, Write};
+
+/// Starting position as a FEN, for the UCI `position startpos` shorthand.
+const STARTPOS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+pub fn run(settings_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = load_settings(settings_path)?;
+
+    let mut chess: Option<Chess> = None;
+    let stdout = io::stdout();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name toyfish");
+                println!("id author toyfish-rs contributors");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => chess = Some(Chess::from_settings(settings.clone())?),
+            Some("position") => {
+                let args: Vec<&str> = tokens.collect();
+                chess = Some(build_position(&settings, &args)?);
+            }
+            Some("go") => {
+                let args: Vec<&str> = tokens.collect();
+                if let Some(chess) = chess.as_mut() {
+                    match (args.first(), args.get(1).and_then(|d| d.parse().ok())) {
+                        (Some(&"perft"), Some(depth)) => {
+                            chess.perft_divide(depth);
+                        }
+                        _ => {
+                            let best = select_move(chess);
+                            println!("bestmove {}", best.as_deref().unwrap_or("0000"));
+                        }
+                    }
+                }
+            }
+            Some("quit") => break,
+            _ => {}
+        }
+
+        stdout.lock().flush()?;
+    }
+
+    Ok(())
+}
+
+/// Builds the position described by a `position startpos|fen <fen> [moves
+/// <move>...]` command, replaying each long-algebraic move against the
+/// legal move list.
+fn build_position(settings: &Settings, args: &[&str]) -> Result<Chess, Box<dyn std::error::Error>> {
+    let moves_at = args.iter().position(|&token| token == "moves");
+    let (position_args, move_args) = match moves_at {
+        Some(index) => (&args[..index], &args[index + 1..]),
+        None => (args, &[][..]),
+    };
+
+    let mut position_settings = settings.clone();
+    position_settings.fen = match position_args.first() {
+        Some(&"fen") => position_args[1..].join(" "),
+        _ => STARTPOS_FEN.to_string(),
+    };
+
+    let mut chess = Chess::from_settings(position_settings)?;
+    for &uci_move in move_args {
+        let legal = chess.generate_legal_moves();
+        if let Some(chosen) = legal.into_iter().find(|m| move_to_uci(m) == uci_move) {
+            chess.make_move(&chosen);
+        }
+    }
+
+    Ok(chess)
+}
+
+/// Fixed search depth used by `go`, since no time management has been
+/// implemented yet.
+const SEARCH_DEPTH: u32 = 4;
+
+/// Picks the move to report for `go` by running the search; returns `None`
+/// when there is no legal move.
+fn select_move(chess: &mut Chess) -> Option<String> {
+    let (best_move, _score) = chess.search(SEARCH_DEPTH);
+    best_move.as_ref().map(move_to_uci)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(algebraic: &str) -> usize {
+        (21..=98)
+            .filter(|&sq| is_valid_square(sq as i32))
+            .find(|&sq| square_to_algebraic(sq) == algebraic)
+            .expect("algebraic square should be on the board")
+    }
+
+    #[test]
+    fn build_position_replays_startpos_and_fen_moves() {
+        let settings = test_settings(STARTPOS_FEN);
+
+        let chess = build_position(&settings, &["startpos", "moves", "e2e4", "e7e5"])
+            .expect("startpos plus moves should build");
+        assert_eq!(chess.board[square("e4")], 'P');
+        assert_eq!(chess.board[square("e5")], 'p');
+        assert_eq!(chess.board[square("e2")], '.');
+
+        let args = ["fen", "8/8/8/4k3/8/8/8/4K3", "w", "-", "-", "0", "1", "moves", "e1d1"];
+        let chess = build_position(&settings, &args).expect("fen plus moves should build");
+        assert_eq!(chess.board[square("d1")], 'K');
+    }
+}